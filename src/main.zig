@@ -5,7 +5,58 @@ const enums = @import("enums.zig");
 
 const allocator = std.heap.c_allocator;
 const DUMP_PACKET: bool = true;
+const NAME_POLICY: NamePolicy = .off; // Action for non-LDH names (leading '_' service labels allowed)
+const MINIMAL_ANY: bool = true; // Answer qtype=ANY with a single HINFO record (RFC 8482)
+const REJECT_MULTI_QUESTION: bool = true; // FORMERR for QDCOUNT > 1 instead of answering each question
+const CHAOS_VERSION: ?[]const u8 = null; // TXT for version.bind/version.server, null to REFUSE
+const CHAOS_HOSTNAME: ?[]const u8 = null; // TXT for hostname.bind/id.server, null to REFUSE
+const ROOT_QUERY: RootQuery = .refuse; // Queries for "." are REFUSED or get the normal answer
+const MAX_UDP_RESPONSE_SIZE: usize = 512; // Larger responses are sent with TC=1 and the question only
+const STATIC_RECORDS = [_]StaticRecord{
+    // .{ .NAME = "router.lan", .QTYPE = .A, .DATA = &.{ 192, 168, 1, 1 } },
+    // .{ .NAME = "*.lan", .QTYPE = .A, .DATA = &.{ 192, 168, 1, 1 } },
+};
 var counter = @as(i32, 0);
+
+// What to do with query names outside the LDH policy. Names with control
+// characters or over-long labels are never answered: .off and .formerr reply
+// FORMERR, .drop sends nothing.
+const NamePolicy = enum { off, formerr, drop };
+
+const RootQuery = enum { refuse, answer };
+
+// Per-response behavior, defaulting to the compile-time settings above.
+const Policy = struct {
+    names: NamePolicy = NAME_POLICY,
+    minimal_any: bool = MINIMAL_ANY,
+    reject_multi_question: bool = REJECT_MULTI_QUESTION,
+    static_records: []const StaticRecord = &STATIC_RECORDS,
+    chaos_version: ?[]const u8 = CHAOS_VERSION,
+    chaos_hostname: ?[]const u8 = CHAOS_HOSTNAME,
+    root_query: RootQuery = ROOT_QUERY,
+};
+
+comptime {
+    if (CHAOS_VERSION) |version| {
+        if (txtDataLength(version) == null) {
+            @compileError("CHAOS_VERSION is too long for a TXT record");
+        }
+    }
+    if (CHAOS_HOSTNAME) |hostname| {
+        if (txtDataLength(hostname) == null) {
+            @compileError("CHAOS_HOSTNAME is too long for a TXT record");
+        }
+    }
+}
+
+// Fixed answer for a name, checked before the default 1.1.1.1 answer.
+const StaticRecord = struct {
+    NAME: []const u8, // "host.lan", or "*.lan" for any name below lan
+    QTYPE: enums.DNSQueryType, // Also served for any qtype when CNAME
+    TTL: u32 = 300,
+    DATA: []const u8, // RDATA in wire format
+};
+
 const DNSPacket = struct {
     ID: u16, // ID
     QR: u1, // 0 for Query, 1 for Response
@@ -65,11 +116,7 @@ fn writeQuestion(q: DNSQuestion, data: *std.ArrayList(u8)) !void {
 fn writeAnswer(res: Answer, data: *std.ArrayList(u8)) !void {
     var bw = std.io.bitWriter(.Big, data.writer());
 
-    if (res.QNAME.len == 0) {
-        std.log.info("Empty name", .{});
-    } else {
-        try write_qname(data, res.QNAME);
-    }
+    try write_qname(data, res.QNAME);
     try bw.writeBits(@as(u16, @intFromEnum(res.QTYPE)), 16);
     try bw.writeBits(@as(u16, @intFromEnum(res.QCLASS)), 16);
     try bw.writeBits(@as(u32, @truncate(res.TTL)), 32);
@@ -147,8 +194,8 @@ pub fn main() !void {
         var infr = try std.fmt.allocPrint(allocator, "{}.in_recr.bin", .{counter});
         var outf = try std.fmt.allocPrint(allocator, "{}.out.bin", .{counter});
         var recr = try packet.toBytes();
-        var resp = try generateResponse(packet);
-        var rb = try resp.toBytes();
+        var resp = (try generateResponse(packet, .{})) orelse continue;
+        var rb = try udpResponse(resp, MAX_UDP_RESPONSE_SIZE);
         try writeArray(&buf, rf.numberOfBytes, inf);
         try writeArray(recr, recr.len, infr);
         std.debug.print("orig:{}\n", .{std.fmt.fmtSliceHexUpper(buf[0..recr.len])});
@@ -159,24 +206,40 @@ pub fn main() !void {
     }
 }
 
-fn generateResponse(packet: DNSPacket) !DNSPacket {
+// Returns null when the query should be dropped without a reply.
+fn generateResponse(packet: DNSPacket, policy: Policy) !?DNSPacket {
     var resp = packet;
     resp.QR = 1;
     resp.RA = packet.RD;
-    resp.ANCOUNT = resp.QDCOUNT;
     resp.NSCOUNT = 0;
     resp.ARCOUNT = 0;
+    if (policy.reject_multi_question and packet.Questions.len > 1) {
+        return errorResponse(resp, .INVALID_FORMAT);
+    }
+    var qidx: usize = 0;
+    while (qidx < packet.Questions.len) : (qidx += 1) {
+        if (!isValidName(packet.Questions[qidx].QNAME, policy.names != .off)) {
+            if (policy.names == .drop) {
+                return null;
+            }
+            return errorResponse(resp, .INVALID_FORMAT);
+        }
+    }
+    resp.ANCOUNT = resp.QDCOUNT;
     var anidx: usize = 0;
     var answers = try allocator.alloc(Answer, resp.QDCOUNT);
     while (anidx < resp.QDCOUNT and resp.QDCOUNT != 0) : (anidx += 1) {
-        answers[anidx] = Answer{
-            .QNAME = packet.Questions[anidx].QNAME,
-            .DATA_LENGTH = 4,
-            .DATA = @constCast(&[4]u8{ 1, 1, 1, 1 }),
-            .QTYPE = .A,
-            .QCLASS = .IN,
-            .TTL = 1,
-        };
+        var q = packet.Questions[anidx];
+        if (q.QNAME.len == 0 and policy.root_query == .refuse) {
+            return errorResponse(resp, .POLICY_FAIL);
+        }
+        if (q.QCLASS == .CH) {
+            answers[anidx] = (try chaosAnswer(q, policy.chaos_version, policy.chaos_hostname)) orelse {
+                return errorResponse(resp, .POLICY_FAIL);
+            };
+            continue;
+        }
+        answers[anidx] = answerFor(q, policy);
     }
     resp.Answers = answers;
 
@@ -184,6 +247,164 @@ fn generateResponse(packet: DNSPacket) !DNSPacket {
     return resp;
 }
 
+fn answerFor(q: DNSQuestion, policy: Policy) Answer {
+    var ridx: usize = 0;
+    while (ridx < policy.static_records.len) : (ridx += 1) {
+        var rec = policy.static_records[ridx];
+        if ((rec.QTYPE == q.QTYPE or rec.QTYPE == .CNAME) and staticNameMatches(q.QNAME, rec.NAME)) {
+            return Answer{
+                .QNAME = q.QNAME,
+                .DATA_LENGTH = @intCast(rec.DATA.len),
+                .DATA = @constCast(rec.DATA),
+                .QTYPE = rec.QTYPE,
+                .QCLASS = .IN,
+                .TTL = rec.TTL,
+            };
+        }
+    }
+    if (policy.minimal_any and q.QTYPE == .ALL) {
+        // CPU "RFC8482", empty OS
+        const hinfo = "\x07RFC8482\x00";
+        return Answer{
+            .QNAME = q.QNAME,
+            .DATA_LENGTH = hinfo.len,
+            .DATA = @constCast(hinfo),
+            .QTYPE = .HINFO,
+            .QCLASS = .IN,
+            .TTL = 3789, // Same TTL Cloudflare uses for its RFC 8482 answers
+        };
+    }
+    return Answer{
+        .QNAME = q.QNAME,
+        .DATA_LENGTH = 4,
+        .DATA = @constCast(&[4]u8{ 1, 1, 1, 1 }),
+        .QTYPE = .A,
+        .QCLASS = .IN,
+        .TTL = 1,
+    };
+}
+
+// Returns null when the query should be REFUSED.
+fn chaosAnswer(q: DNSQuestion, version: ?[]const u8, hostname: ?[]const u8) !?Answer {
+    if (q.QTYPE != .TXT) {
+        return null;
+    }
+    var value: ?[]const u8 = null;
+    if (nameIs(q.QNAME, "version.bind") or nameIs(q.QNAME, "version.server")) {
+        value = version;
+    } else if (nameIs(q.QNAME, "hostname.bind") or nameIs(q.QNAME, "id.server")) {
+        value = hostname;
+    }
+    var text = value orelse return null;
+    var data_len = txtDataLength(text) orelse return error.ChaosValueTooLong;
+    var data = try allocator.alloc(u8, data_len);
+    var pos: usize = 0;
+    var off: usize = 0;
+    while (pos < data.len) {
+        var n: usize = @min(255, text.len - off);
+        data[pos] = @truncate(n);
+        @memcpy(data[pos + 1 .. pos + 1 + n], text[off .. off + n]);
+        pos += n + 1;
+        off += n;
+    }
+    return Answer{
+        .QNAME = q.QNAME,
+        .DATA_LENGTH = data_len,
+        .DATA = data,
+        .QTYPE = .TXT,
+        .QCLASS = .CH,
+        .TTL = 0,
+    };
+}
+
+// TXT RDATA is a sequence of character-strings of at most 255 bytes each.
+// Returns null when `text` does not fit in a single record.
+fn txtDataLength(text: []const u8) ?u16 {
+    var strings = @max(1, (text.len + 254) / 255);
+    return std.math.cast(u16, text.len + strings);
+}
+
+fn staticNameMatches(qname: [][]u8, pattern: []const u8) bool {
+    if (!std.mem.startsWith(u8, pattern, "*.")) {
+        return nameIs(qname, pattern);
+    }
+    var suffix = pattern[2..];
+    var suffix_labels = std.mem.count(u8, suffix, ".") + 1;
+    if (qname.len <= suffix_labels) {
+        return false;
+    }
+    return nameIs(qname[qname.len - suffix_labels ..], suffix);
+}
+
+fn nameIs(qname: [][]u8, name: []const u8) bool {
+    var labels = std.mem.splitScalar(u8, name, '.');
+    var cnt: usize = 0;
+    while (labels.next()) |label| : (cnt += 1) {
+        if (cnt >= qname.len or !std.ascii.eqlIgnoreCase(qname[cnt], label)) {
+            return false;
+        }
+    }
+    return cnt == qname.len;
+}
+
+// Serializes `resp`, or a truncated (TC=1) copy with only the question section
+// when it would not fit in `max_size` bytes.
+fn udpResponse(resp: DNSPacket, max_size: usize) ![]u8 {
+    var rb = try resp.toBytes();
+    if (rb.len <= max_size) {
+        return rb;
+    }
+    var tc = resp;
+    tc.TC = 1;
+    tc.ANCOUNT = 0;
+    tc.Answers = resp.Answers[0..0];
+    return tc.toBytes();
+}
+
+fn errorResponse(resp: DNSPacket, rcode: enums.DNSResponseCode) DNSPacket {
+    var err = resp;
+    err.RCODE = rcode;
+    err.ANCOUNT = 0;
+    err.Answers = resp.Answers[0..0];
+    return err;
+}
+
+// Rejects labels over 63 bytes, names over 255 bytes in wire form, and
+// non-printable bytes. With `strict`, labels must also be LDH (letters, digits,
+// inner hyphens), allowing a leading '_' for service labels such as _dmarc or _sip.
+fn isValidName(qname: [][]u8, strict: bool) bool {
+    var wire_len: usize = 1; // root label
+    var cnt: usize = 0;
+    while (cnt < qname.len) : (cnt += 1) {
+        var label = qname[cnt];
+        if (label.len > 63) {
+            return false;
+        }
+        wire_len += label.len + 1;
+        if (wire_len > 255) {
+            return false;
+        }
+        var i: usize = 0;
+        while (i < label.len) : (i += 1) {
+            var c = label[i];
+            if (!std.ascii.isPrint(c)) {
+                return false;
+            }
+            if (!strict or std.ascii.isAlphanumeric(c)) {
+                continue;
+            }
+            if (c == '-' and i != 0 and i != label.len - 1) {
+                continue;
+            }
+            if (c == '_' and i == 0) {
+                continue;
+            }
+            return false;
+        }
+    }
+    return true;
+}
+
 fn parsePacket(data: []u8) !DNSPacket {
     var reader = std.io.fixedBufferStream(data[0..]); // Convert to a reader.
     var bit_reader = std.io.bitReader(std.builtin.Endian.Big, reader.reader());
@@ -294,3 +515,260 @@ fn writeArray(buf: []u8, len: usize, filename: []u8) !void {
 }
 
 test "simple test" {}
+
+fn testQuery(questions: []DNSQuestion) !DNSPacket {
+    return DNSPacket{
+        .ID = 1,
+        .QR = 0,
+        .OP = .QUERY,
+        .AA = 0,
+        .TC = 0,
+        .RD = 1,
+        .RA = 0,
+        .Z = 0,
+        .RCODE = .NO_ERROR,
+        .QDCOUNT = @truncate(questions.len),
+        .ANCOUNT = 0,
+        .NSCOUNT = 0,
+        .ARCOUNT = 0,
+        .Questions = questions,
+        .Answers = try allocator.alloc(Answer, 0),
+    };
+}
+
+test "name policy rejects control characters" {
+    var nul = "ex\x00ample".*;
+    var ctl = "ex\x07ample".*;
+    var tld = "com".*;
+    var with_nul = [_][]u8{ &nul, &tld };
+    var with_ctl = [_][]u8{ &ctl, &tld };
+    try std.testing.expect(!isValidName(&with_nul, false));
+    try std.testing.expect(!isValidName(&with_ctl, false));
+    try std.testing.expect(!isValidName(&with_ctl, true));
+}
+
+test "name policy accepts service labels" {
+    var svc = "_dmarc".*;
+    var host = "mail-1".*;
+    var tld = "com".*;
+    var name = [_][]u8{ &svc, &host, &tld };
+    try std.testing.expect(isValidName(&name, true));
+
+    var bad = "-mail".*;
+    var bad_name = [_][]u8{ &bad, &tld };
+    try std.testing.expect(!isValidName(&bad_name, true));
+}
+
+test "name policy action" {
+    var host = "foo_bar".*;
+    var tld = "com".*;
+    var name = [_][]u8{ &host, &tld };
+    var questions = [_]DNSQuestion{.{ .QNAME = &name, .QTYPE = .A, .QCLASS = .IN }};
+
+    var resp = (try generateResponse(try testQuery(&questions), .{ .names = .off })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.NO_ERROR, resp.RCODE);
+    try std.testing.expectEqual(@as(u16, 1), resp.ANCOUNT);
+    resp = (try generateResponse(try testQuery(&questions), .{ .names = .formerr })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.INVALID_FORMAT, resp.RCODE);
+    try std.testing.expect((try generateResponse(try testQuery(&questions), .{ .names = .drop })) == null);
+
+    var ctl = "ex\x07ample".*;
+    questions[0].QNAME[0] = &ctl;
+    resp = (try generateResponse(try testQuery(&questions), .{ .names = .off })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.INVALID_FORMAT, resp.RCODE);
+    try std.testing.expect((try generateResponse(try testQuery(&questions), .{ .names = .drop })) == null);
+}
+
+test "name policy enforces label and name length" {
+    var long_label = [_]u8{'a'} ** 64;
+    var tld = "com".*;
+    var with_long_label = [_][]u8{ &long_label, &tld };
+    try std.testing.expect(!isValidName(&with_long_label, false));
+
+    // 3 * (63 + 1) + (61 + 1) + 1 = 255 bytes on the wire
+    var a = [_]u8{'a'} ** 63;
+    var b = [_]u8{'b'} ** 63;
+    var c = [_]u8{'c'} ** 63;
+    var fits = [_]u8{'d'} ** 61;
+    var too_long = [_]u8{'d'} ** 62;
+    var max_name = [_][]u8{ &a, &b, &c, &fits };
+    var long_name = [_][]u8{ &a, &b, &c, &too_long };
+    try std.testing.expect(isValidName(&max_name, true));
+    try std.testing.expect(!isValidName(&long_name, false));
+
+    var questions = [_]DNSQuestion{.{ .QNAME = &long_name, .QTYPE = .A, .QCLASS = .IN }};
+    var resp = (try generateResponse(try testQuery(&questions), .{ .names = .off })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.INVALID_FORMAT, resp.RCODE);
+    try std.testing.expectEqual(@as(u16, 0), resp.ANCOUNT);
+}
+
+test "ANY query gets a single HINFO answer" {
+    var host = "example".*;
+    var tld = "com".*;
+    var name = [_][]u8{ &host, &tld };
+    var questions = [_]DNSQuestion{.{ .QNAME = &name, .QTYPE = .ALL, .QCLASS = .ANY }};
+    var resp = (try generateResponse(try testQuery(&questions), .{ .minimal_any = true })).?;
+    try std.testing.expectEqual(@as(u16, 1), resp.ANCOUNT);
+    try std.testing.expectEqual(@as(usize, 1), resp.Answers.len);
+    try std.testing.expectEqual(enums.DNSQueryType.HINFO, resp.Answers[0].QTYPE);
+    try std.testing.expectEqual(enums.DNSClassType.IN, resp.Answers[0].QCLASS);
+    try std.testing.expectEqualSlices(u8, "\x07RFC8482\x00", resp.Answers[0].DATA);
+
+    resp = (try generateResponse(try testQuery(&questions), .{ .minimal_any = false })).?;
+    try std.testing.expectEqual(@as(u16, 1), resp.ANCOUNT);
+    try std.testing.expectEqual(enums.DNSQueryType.A, resp.Answers[0].QTYPE);
+}
+
+test "root ANY query serializes the root owner name" {
+    var root = [_][]u8{};
+    var questions = [_]DNSQuestion{.{ .QNAME = &root, .QTYPE = .ALL, .QCLASS = .IN }};
+    var resp = (try generateResponse(try testQuery(&questions), .{ .minimal_any = true, .root_query = .answer })).?;
+    var rb = try resp.toBytes();
+    // 12 header + 5 question + 11 answer fixed part + 9 HINFO rdata
+    try std.testing.expectEqual(@as(usize, 37), rb.len);
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x00, 0xFF, 0x00, 0x01 }, rb[12..17]);
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x00, 0x0D, 0x00, 0x01, 0x00, 0x00, 0x0E, 0xCD, 0x00, 0x09 }, rb[17..28]);
+    try std.testing.expectEqualSlices(u8, "\x07RFC8482\x00", rb[28..]);
+}
+
+test "multiple questions policy" {
+    var host = "example".*;
+    var tld = "com".*;
+    var name = [_][]u8{ &host, &tld };
+    var questions = [_]DNSQuestion{
+        .{ .QNAME = &name, .QTYPE = .A, .QCLASS = .IN },
+        .{ .QNAME = &name, .QTYPE = .AAAA, .QCLASS = .IN },
+    };
+    var resp = (try generateResponse(try testQuery(&questions), .{ .reject_multi_question = true })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.INVALID_FORMAT, resp.RCODE);
+    try std.testing.expectEqual(@as(u16, 0), resp.ANCOUNT);
+    try std.testing.expectEqual(@as(usize, 0), resp.Answers.len);
+
+    resp = (try generateResponse(try testQuery(&questions), .{ .reject_multi_question = false })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.NO_ERROR, resp.RCODE);
+    try std.testing.expectEqual(@as(u16, 2), resp.ANCOUNT);
+    try std.testing.expectEqual(@as(usize, 2), resp.Answers.len);
+}
+
+test "CHAOS version.bind and hostname.bind" {
+    var version = "VERSION".*;
+    var hostname = "HOSTNAME".*;
+    var id = "id".*;
+    var server = "server".*;
+    var bind = "bind".*;
+    var version_bind = [_][]u8{ &version, &bind };
+    var hostname_bind = [_][]u8{ &hostname, &bind };
+    var id_server = [_][]u8{ &id, &server };
+
+    var questions = [_]DNSQuestion{.{ .QNAME = &version_bind, .QTYPE = .TXT, .QCLASS = .CH }};
+    const hidden = Policy{ .chaos_version = null, .chaos_hostname = null };
+    const shown = Policy{ .chaos_version = "heimdall", .chaos_hostname = "node-1" };
+    var resp = (try generateResponse(try testQuery(&questions), hidden)).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.POLICY_FAIL, resp.RCODE);
+    resp = (try generateResponse(try testQuery(&questions), shown)).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.NO_ERROR, resp.RCODE);
+    try std.testing.expectEqualSlices(u8, "\x08heimdall", resp.Answers[0].DATA);
+    questions[0].QNAME = &hostname_bind;
+    resp = (try generateResponse(try testQuery(&questions), hidden)).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.POLICY_FAIL, resp.RCODE);
+    resp = (try generateResponse(try testQuery(&questions), shown)).?;
+    try std.testing.expectEqualSlices(u8, "\x06node-1", resp.Answers[0].DATA);
+
+    var ans = (try chaosAnswer(.{ .QNAME = &version_bind, .QTYPE = .TXT, .QCLASS = .CH }, "heimdall", "node-1")).?;
+    try std.testing.expectEqualSlices(u8, "\x08heimdall", ans.DATA);
+    ans = (try chaosAnswer(.{ .QNAME = &id_server, .QTYPE = .TXT, .QCLASS = .CH }, "heimdall", "node-1")).?;
+    try std.testing.expectEqualSlices(u8, "\x06node-1", ans.DATA);
+    try std.testing.expect((try chaosAnswer(.{ .QNAME = &hostname_bind, .QTYPE = .A, .QCLASS = .CH }, "heimdall", "node-1")) == null);
+
+    const long_version = "v" ** 300;
+    ans = (try chaosAnswer(.{ .QNAME = &version_bind, .QTYPE = .TXT, .QCLASS = .CH }, long_version, null)).?;
+    try std.testing.expectEqual(@as(u16, 302), ans.DATA_LENGTH);
+    try std.testing.expectEqual(@as(usize, 302), ans.DATA.len);
+    try std.testing.expectEqual(@as(u8, 255), ans.DATA[0]);
+    try std.testing.expectEqual(@as(u8, 45), ans.DATA[256]);
+    try std.testing.expectEqualSlices(u8, long_version[255..], ans.DATA[257..]);
+
+    ans = (try chaosAnswer(.{ .QNAME = &version_bind, .QTYPE = .TXT, .QCLASS = .CH }, "", null)).?;
+    try std.testing.expectEqualSlices(u8, "\x00", ans.DATA);
+
+    try std.testing.expectEqual(@as(?u16, 65535), txtDataLength("v" ** 65279));
+    try std.testing.expectEqual(@as(?u16, null), txtDataLength("v" ** 65280));
+}
+
+test "root query policy" {
+    var root = [_][]u8{};
+    var questions = [_]DNSQuestion{.{ .QNAME = &root, .QTYPE = .A, .QCLASS = .IN }};
+
+    var resp = (try generateResponse(try testQuery(&questions), .{ .root_query = .refuse })).?;
+    try std.testing.expectEqual(enums.DNSResponseCode.POLICY_FAIL, resp.RCODE);
+    var rb = try resp.toBytes();
+    // Header and the root question only: 0x00, type A, class IN
+    try std.testing.expectEqual(@as(usize, 17), rb.len);
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x00, 0x01, 0x00, 0x01 }, rb[12..17]);
+
+    resp = (try generateResponse(try testQuery(&questions), .{ .root_query = .answer })).?;
+    try std.testing.expectEqual(@as(u16, 1), resp.ANCOUNT);
+    rb = try resp.toBytes();
+    // Answer RR owner is the single root label, followed by type A, class IN
+    try std.testing.expectEqual(@as(usize, 17 + 15), rb.len);
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x00, 0x01, 0x00, 0x01 }, rb[17..22]);
+}
+
+test "oversized UDP response is truncated" {
+    // 255-byte name: 12 header + 259 question + 269 answer = 540 bytes
+    var a = [_]u8{'a'} ** 63;
+    var b = [_]u8{'b'} ** 63;
+    var c = [_]u8{'c'} ** 63;
+    var d = [_]u8{'d'} ** 61;
+    var name = [_][]u8{ &a, &b, &c, &d };
+    var questions = [_]DNSQuestion{.{ .QNAME = &name, .QTYPE = .A, .QCLASS = .IN }};
+    var full = (try generateResponse(try testQuery(&questions), .{})).?;
+    try std.testing.expectEqual(@as(u16, 1), full.ANCOUNT);
+
+    var rb = try udpResponse(full, 512);
+    try std.testing.expectEqual(@as(usize, 12 + 259), rb.len);
+    try std.testing.expect(rb[2] & 0x02 != 0); // TC
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x01 }, rb[4..6]); // QDCOUNT
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x00 }, rb[6..8]); // ANCOUNT
+
+    var short = "example".*;
+    var tld = "com".*;
+    var short_name = [_][]u8{ &short, &tld };
+    questions[0].QNAME = &short_name;
+    rb = try udpResponse((try generateResponse(try testQuery(&questions), .{})).?, 512);
+    try std.testing.expect(rb[2] & 0x02 == 0);
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 0x00, 0x01 }, rb[6..8]);
+}
+
+test "static records exact and wildcard" {
+    const records = [_]StaticRecord{
+        .{ .NAME = "router.lan", .QTYPE = .A, .DATA = &.{ 192, 168, 1, 254 } },
+        .{ .NAME = "*.lan", .QTYPE = .A, .DATA = &.{ 192, 168, 1, 1 } },
+        .{ .NAME = "www.example.com", .QTYPE = .CNAME, .DATA = "\x03cdn\x07example\x03net\x00" },
+    };
+    var router = "router".*;
+    var nas = "nas".*;
+    var office = "office".*;
+    var lan = "lan".*;
+    var www = "www".*;
+    var example = "example".*;
+    var com = "com".*;
+    var router_lan = [_][]u8{ &router, &lan };
+    var nas_office_lan = [_][]u8{ &nas, &office, &lan };
+    var bare_lan = [_][]u8{&lan};
+    var www_example = [_][]u8{ &www, &example, &com };
+
+    var ans = answerFor(.{ .QNAME = &router_lan, .QTYPE = .A, .QCLASS = .IN }, .{ .static_records = &records });
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 192, 168, 1, 254 }, ans.DATA);
+    ans = answerFor(.{ .QNAME = &nas_office_lan, .QTYPE = .A, .QCLASS = .IN }, .{ .static_records = &records });
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 192, 168, 1, 1 }, ans.DATA);
+    try std.testing.expectEqual(@as(u32, 300), ans.TTL);
+
+    // The wildcard covers names below lan, not lan itself
+    ans = answerFor(.{ .QNAME = &bare_lan, .QTYPE = .A, .QCLASS = .IN }, .{ .static_records = &records });
+    try std.testing.expectEqualSlices(u8, &[_]u8{ 1, 1, 1, 1 }, ans.DATA);
+
+    ans = answerFor(.{ .QNAME = &www_example, .QTYPE = .AAAA, .QCLASS = .IN }, .{ .static_records = &records });
+    try std.testing.expectEqual(enums.DNSQueryType.CNAME, ans.QTYPE);
+    try std.testing.expectEqual(@as(u16, 17), ans.DATA_LENGTH);
+}